@@ -2,9 +2,6 @@ use crate::sections::as_u16_be;
 use crate::sections::as_u32_be;
 use failure::{Error, Fail};
 use std::collections::HashMap;
-use std::io::Cursor;
-use std::io::Read;
-use std::sync::mpsc::channel;
 
 /// The LayerAndMaskInformationSection comes from the bytes in the fourth section of the PSD.
 ///
@@ -35,39 +32,181 @@ use std::sync::mpsc::channel;
 /// | Variable | (Photoshop 4.0 and later) <br> Series of tagged blocks containing various types of data. See See Additional Layer Information for the list of the types of data that can be included here. |
 #[derive(Debug)]
 pub struct LayerAndMaskInformationSection {
-    pub(in crate) layers: HashMap<String, PsdLayer>,
+    /// The layers and groups of this PSD, reconstructed into the hierarchy that Photoshop
+    /// displays in its layers panel.
+    pub(in crate) layers: Vec<PsdGroupMember>,
 }
 
 impl LayerAndMaskInformationSection {
     /// Create a LayerAndMaskInformationSection from the bytes in the corresponding secton in a
     /// PSD file.
-    pub fn from_bytes(bytes: &[u8]) -> Result<LayerAndMaskInformationSection, Error> {
-        let mut cursor = Cursor::new(bytes);
-
-        let mut two_bytes = [0; 2];
-        let mut four_bytes = [0; 4];
+    ///
+    /// `variant` tells us whether `bytes` came from a PSD or a PSB file, since PSB widens some
+    /// of the length fields from 4 to 8 bytes. `color_mode` tells us how to interpret each
+    /// layer's channel ids, since they are color-mode dependent (e.g. CMYK uses ids 0-3 where
+    /// RGB uses 0-2). `depth` tells us how many bytes each channel sample occupies, which raw
+    /// (uncompressed) channel data needs in order to know how many bytes to read.
+    pub fn from_bytes(
+        bytes: &[u8],
+        variant: PsdFileVariant,
+        color_mode: PsdColorMode,
+        depth: PsdDepth,
+    ) -> Result<LayerAndMaskInformationSection, Error> {
+        let mut cursor = PsdCursor::new(bytes);
 
-        // The first four bytes of the section is the length marker for the layer and mask
+        // The first bytes of the section are the length marker for the layer and mask
         // information section, we won't be needing it.
-        cursor.read_exact(&mut four_bytes)?;
+        read_section_length(&mut cursor, variant)?;
 
-        // Read the next four bytes to get the length of the layer info section
-        cursor.read_exact(&mut four_bytes)?;
-        let layer_info_section_len = as_u32_be(&four_bytes);
+        // Read the next bytes to get the length of the layer info section
+        let _layer_info_section_len = read_section_length(&mut cursor, variant)?;
 
         // Next 2 bytes is the layer count
-        cursor.read_exact(&mut two_bytes)?;
-        let layer_count = as_u16_be(&two_bytes);
+        let layer_count = cursor.read_u16_be()?;
+
+        // Read each layer record. The channel image data for all of the layers comes after all
+        // of the layer records, so we hang on to the records and come back for their pixels
+        // once we know where every layer's channels begin and end.
+        let mut layer_records = Vec::with_capacity(layer_count as usize);
+        for _ in 0..layer_count {
+            let layer_record = read_layer_record(&mut cursor, variant, color_mode)?;
+            layer_records.push(layer_record);
+        }
+
+        // Now read the channel image data, in the same order as the channels were declared in
+        // each layer record, pairing each record with its finished PsdLayer.
+        let mut records_and_layers = Vec::with_capacity(layer_records.len());
+        for layer_record in layer_records {
+            let width = layer_record.width();
+            let height = layer_record.height();
+
+            let mut channels = HashMap::new();
+            for (channel_id, channel_length) in &layer_record.channels {
+                let channel_bytes = cursor.read(*channel_length as usize)?;
+                let channel_bytes =
+                    decode_channel_data(channel_bytes, variant, depth, width, height)?;
+                channels.insert(*channel_id, channel_bytes);
+            }
+
+            let psd_layer = PsdLayer {
+                name: layer_record.name.clone(),
+                channels,
+                blend_mode: layer_record.blend_mode,
+                opacity: layer_record.opacity,
+                clipping: layer_record.clipping,
+                transparency_protected: layer_record.transparency_protected,
+                visible: layer_record.visible,
+                pixel_data_irrelevant: layer_record.pixel_data_irrelevant,
+                layer_id: layer_record.layer_id,
+                section_divider: layer_record.section_divider,
+                color_label: layer_record.color_label,
+            };
+
+            records_and_layers.push((layer_record, psd_layer));
+        }
 
-        // Read each layer
-        for layer_num in 0..layer_count {
-            let layer = read_layer_record(bytes, &mut cursor)?;
+        let layers = build_layer_tree(records_and_layers);
+
+        Ok(LayerAndMaskInformationSection { layers })
+    }
+}
+
+/// Reconstruct the nested group/folder hierarchy from a flat, bottom-to-top list of layer
+/// records and their decoded pixels.
+///
+/// PSD stores layers bottom-to-top, and groups are delimited by a bounding section divider
+/// (type 3, a hidden sentinel layer) at the bottom of the group's contents and the folder's own
+/// layer (type 1 = open, type 2 = closed) at the top of its contents. We scan in order while
+/// maintaining a stack of in-progress groups: a bounding divider opens a new group, and the
+/// following type 1/2 layer closes the group that is on top of the stack, using its own name
+/// and properties as the folder's.
+fn build_layer_tree(records_and_layers: Vec<(LayerRecord, PsdLayer)>) -> Vec<PsdGroupMember> {
+    let mut top_level = vec![];
+    let mut open_groups: Vec<Vec<PsdGroupMember>> = vec![];
+
+    for (layer_record, psd_layer) in records_and_layers {
+        match layer_record.section_divider {
+            Some(SectionDivider::BoundingSectionDivider) => {
+                open_groups.push(vec![]);
+            }
+            Some(SectionDivider::OpenFolder) | Some(SectionDivider::ClosedFolder) => {
+                let children = open_groups.pop().unwrap_or_default();
+                let opened = layer_record.section_divider == Some(SectionDivider::OpenFolder);
+
+                let group = PsdGroupMember::Group(PsdGroupLayer {
+                    name: layer_record.name,
+                    opened,
+                    layer_id: layer_record.layer_id,
+                    color_label: layer_record.color_label,
+                    children,
+                });
+
+                push_group_member(&mut open_groups, &mut top_level, group);
+            }
+            Some(SectionDivider::Layer) | None => {
+                push_group_member(&mut open_groups, &mut top_level, PsdGroupMember::Layer(psd_layer));
+            }
         }
+    }
+
+    top_level
+}
+
+/// Push a finished layer or group into whichever group is currently open, or onto the
+/// top-level list if there is no group currently open.
+fn push_group_member(
+    open_groups: &mut Vec<Vec<PsdGroupMember>>,
+    top_level: &mut Vec<PsdGroupMember>,
+    member: PsdGroupMember,
+) {
+    match open_groups.last_mut() {
+        Some(children) => children.push(member),
+        None => top_level.push(member),
+    }
+}
+
+/// A layer or a group of layers, as reconstructed from the section divider tagged blocks.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum PsdGroupMember {
+    Layer(PsdLayer),
+    Group(PsdGroupLayer),
+}
+
+/// A group (folder) of layers and/or nested groups.
+#[derive(Debug)]
+pub struct PsdGroupLayer {
+    name: String,
+    opened: bool,
+    layer_id: Option<u32>,
+    color_label: Option<LayerColorLabel>,
+    children: Vec<PsdGroupMember>,
+}
+
+impl PsdGroupLayer {
+    /// The name of the group
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the group is expanded (open) or collapsed (closed) in Photoshop's layers panel
+    pub fn opened(&self) -> bool {
+        self.opened
+    }
+
+    /// The group's id, if the 'lyid' tagged block was present
+    pub fn layer_id(&self) -> Option<u32> {
+        self.layer_id
+    }
 
-        // inside of read_layer method skip over data that we don't need right now, but
-        // leave a comment
+    /// The group's color label, if the 'lclr' tagged block was present
+    pub fn color_label(&self) -> Option<LayerColorLabel> {
+        self.color_label
+    }
 
-        unimplemented!();
+    /// The layers and nested groups that this group contains
+    pub fn children(&self) -> &[PsdGroupMember] {
+        &self.children
     }
 }
 
@@ -97,60 +236,439 @@ impl LayerAndMaskInformationSection {
 /// | Variable               | Layer mask data: See See Layer mask / adjustment layer data for structure. Can be 40 bytes, 24 bytes, or 4 bytes if no layer mask.                                                                                                                                                                                                                                                                                                                                                                                                                                                                |
 /// | Variable               | Layer blending ranges: See See Layer blending ranges data.                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        |
 /// | Variable               | Layer name: Pascal string, padded to a multiple of 4 bytes.                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                       |
-fn read_layer_record(bytes: &[u8], cursor: &mut Cursor<&[u8]>) -> Result<LayerRecord, Error> {
+fn read_layer_record(
+    cursor: &mut PsdCursor,
+    variant: PsdFileVariant,
+    color_mode: PsdColorMode,
+) -> Result<LayerRecord, Error> {
     let mut channels = vec![];
 
-    // TODO: Create a PsdCursor that provides an easy API for skipping bytes.
-    // cursor.skip(2)
-    // let two_bytes: &[u8] = cursor.read(2) (advances cursor by 2)
-    let mut one_byte = [0; 1];
-    let mut two_bytes = [0; 2];
-    let mut four_bytes = [0; 4];
-    let mut six_bytes = [0; 6];
+    // The rectangle containing the contents of the layer, we need this in order to know how
+    // many pixels are in each of its channels. We validate it eagerly so that a malformed
+    // rectangle (right < left, or bottom < top) becomes a parse error here instead of an
+    // overflow panic or a huge bogus width/height later on, in `LayerRecord::width`/`height`.
+    let layer_top = cursor.read_u32_be()? as i32;
+    let layer_left = cursor.read_u32_be()? as i32;
+    let layer_bottom = cursor.read_u32_be()? as i32;
+    let layer_right = cursor.read_u32_be()? as i32;
 
-    // We do not currently parse the layer rectangle, skip it
-    let rectangle_bytes = 16;
-    cursor.set_position(cursor.position() + rectangle_bytes);
+    // The coordinates are signed, so an inverted rectangle is caught even when some of its
+    // coordinates are negative.
+    if (layer_right as i64) < (layer_left as i64) || (layer_bottom as i64) < (layer_top as i64) {
+        Err(LayerRecordError::InvalidRectangle {
+            top: layer_top,
+            left: layer_left,
+            bottom: layer_bottom,
+            right: layer_right,
+        })?;
+    }
 
     // Get the number of channels in the layer
-    cursor.read(&mut two_bytes)?;
-    let channel_count = as_u16_be(&two_bytes);
+    let channel_count = cursor.read_u16_be()?;
 
-    // Read the channel information
+    // Read the channel information. The channel id is always 2 bytes, but the length of its
+    // image data is 4 bytes for PSD and 8 bytes for PSB.
     for _ in 0..channel_count {
-        cursor.read_exact(&mut six_bytes);
-        let channel_id = six_bytes[1] as i8;
-        let channel_id = PsdLayerChannel::new(channel_id)?;
+        let channel_id = cursor.read_u16_be()? as i16 as i8;
+        let channel_id = PsdLayerChannel::new(channel_id, color_mode)?;
 
-        let channel_length = as_u32_be(&[six_bytes[2], six_bytes[3], six_bytes[4], six_bytes[5]]);
+        let channel_length = read_section_length(cursor, variant)?;
 
         channels.push((channel_id, channel_length));
     }
 
-    // We do not currently parse the blend mode signature, skip it
-    cursor.read_exact(&mut four_bytes)?;
+    // The blend mode signature is always '8BIM', we don't need it
+    cursor.skip(4)?;
+
+    // The blend mode key tells us how this layer is composited with the layers below it
+    let mut blend_mode_key = [0; 4];
+    blend_mode_key.copy_from_slice(cursor.read(4)?);
+    let blend_mode = BlendMode::new(blend_mode_key)?;
+
+    // Opacity. 0 = transparent ... 255 = opaque
+    let opacity = cursor.read_u8()?;
+
+    // Clipping: 0 = base, 1 = non-base
+    let clipping = cursor.read_u8()? != 0;
+
+    // Flags: bit 0 = transparency protected; bit 1 = visible; bits 3 & 4 set together = pixel
+    // data irrelevant to the appearance of the document
+    let flags = cursor.read_u8()?;
+    let transparency_protected = flags & 0b0000_0001 != 0;
+    let visible = flags & 0b0000_0010 != 0;
+    let pixel_data_irrelevant = flags & 0b0000_1000 != 0 && flags & 0b0001_0000 != 0;
+
+    // We do not currently parse the filler, skip it
+    cursor.skip(1)?;
+
+    // Length of the extra data field: layer mask data + layer blending ranges + layer name +
+    // (Photoshop 4.0 and later) the additional layer information blocks that follow the name.
+    let extra_data_len = cursor.read_u32_be()?;
+    let extra_data_end = cursor.position() + extra_data_len as usize;
+
+    // Layer mask / adjustment layer data. It starts with its own length (0, 20, or 36), which
+    // we use to skip over the rest of the block since we don't parse it yet.
+    let layer_mask_data_len = cursor.read_u32_be()?;
+    cursor.skip(layer_mask_data_len as usize)?;
+
+    // Layer blending ranges data, we don't parse it yet so we use its length to skip over it
+    let layer_blending_ranges_len = cursor.read_u32_be()?;
+    cursor.skip(layer_blending_ranges_len as usize)?;
+
+    // Layer name: Pascal string, padded to a multiple of 4 bytes.
+    let mut name = cursor.read_pascal_string()?;
+
+    // The rest of the extra data field is a series of tagged "Additional Layer Information"
+    // blocks, keyed by a 4 character signature. We dispatch on the key, overriding/augmenting
+    // what we parsed above; unknown keys are skipped using their declared length.
+    let mut layer_id = None;
+    let mut section_divider = None;
+    let mut color_label = None;
+
+    while cursor.position() < extra_data_end {
+        let block = read_additional_layer_info_block(cursor)?;
+
+        match block.key.as_str() {
+            "luni" => name = parse_unicode_layer_name(&block.data)?,
+            "lyid" => layer_id = Some(PsdCursor::new(&block.data).read_u32_be()?),
+            "lsct" | "lsdk" => {
+                let kind = PsdCursor::new(&block.data).read_u32_be()?;
+                section_divider = Some(SectionDivider::new(kind)?)
+            }
+            "lclr" => {
+                let value = PsdCursor::new(&block.data).read_u16_be()?;
+                color_label = Some(LayerColorLabel::new(value)?)
+            }
+            _ => {}
+        }
+    }
+
+    Ok(LayerRecord {
+        name,
+        channels,
+        layer_top,
+        layer_left,
+        layer_bottom,
+        layer_right,
+        blend_mode,
+        opacity,
+        clipping,
+        transparency_protected,
+        visible,
+        pixel_data_irrelevant,
+        layer_id,
+        section_divider,
+        color_label,
+    })
+}
+
+/// A single tagged block from the "Additional Layer Information" series that follows a layer's
+/// name (Photoshop 4.0 and later).
+///
+/// # [Adobe Docs](https://www.adobe.com/devnet-apps/photoshop/fileformatashtml/)
+///
+/// | Length   | Description                                            |
+/// |----------|---------------------------------------------------------|
+/// | 4        | Signature: '8BIM' or, for some keys, '8B64'              |
+/// | 4        | Key that identifies the kind of data that follows        |
+/// | 4        | Length of the data that follows                          |
+/// | Variable | The data, specific to the key                             |
+struct AdditionalLayerInfoBlock {
+    key: String,
+    data: Vec<u8>,
+}
+
+fn read_additional_layer_info_block(cursor: &mut PsdCursor) -> Result<AdditionalLayerInfoBlock, Error> {
+    // Signature: '8BIM' or '8B64', we don't need to distinguish between them
+    cursor.skip(4)?;
+
+    let key = String::from_utf8_lossy(cursor.read(4)?).to_string();
+    let len = cursor.read_u32_be()?;
+    let data = cursor.read(len as usize)?.to_vec();
+
+    Ok(AdditionalLayerInfoBlock { key, data })
+}
+
+/// Parse the data of a 'luni' tagged block: a Unicode (UTF-16BE) layer name, which should be
+/// used in place of the Pascal string name when present.
+///
+/// | Length          | Description                      |
+/// |-----------------|------------------------------------|
+/// | 4               | The number of characters in the name |
+/// | 2 * # of characters | The name, as UTF-16BE               |
+fn parse_unicode_layer_name(data: &[u8]) -> Result<String, Error> {
+    let mut cursor = PsdCursor::new(data);
+
+    let char_count = cursor.read_u32_be()? as usize;
+    let utf16_bytes = cursor.read(char_count * 2)?;
+
+    let utf16_units: Vec<u16> = utf16_bytes
+        .chunks(2)
+        .map(|bytes| as_u16_be(&[bytes[0], bytes[1]]))
+        .collect();
+
+    Ok(String::from_utf16_lossy(&utf16_units))
+}
+
+/// The section divider type carried by an 'lsct'/'lsdk' tagged block, used to reconstruct
+/// layer group/folder structure.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum SectionDivider {
+    Layer,
+    OpenFolder,
+    ClosedFolder,
+    BoundingSectionDivider,
+}
+
+impl SectionDivider {
+    /// Create a new SectionDivider from its section divider type id
+    pub fn new(kind: u32) -> Result<SectionDivider, Error> {
+        match kind {
+            0 => Ok(SectionDivider::Layer),
+            1 => Ok(SectionDivider::OpenFolder),
+            2 => Ok(SectionDivider::ClosedFolder),
+            3 => Ok(SectionDivider::BoundingSectionDivider),
+            _ => Err(AdditionalLayerInfoError::InvalidSectionDividerType { kind })?,
+        }
+    }
+}
+
+/// The color label carried by an 'lclr' tagged block.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum LayerColorLabel {
+    None,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Violet,
+    Gray,
+}
+
+impl LayerColorLabel {
+    /// Create a new LayerColorLabel from its enumerated value
+    pub fn new(value: u16) -> Result<LayerColorLabel, Error> {
+        match value {
+            0 => Ok(LayerColorLabel::None),
+            1 => Ok(LayerColorLabel::Red),
+            2 => Ok(LayerColorLabel::Orange),
+            3 => Ok(LayerColorLabel::Yellow),
+            4 => Ok(LayerColorLabel::Green),
+            5 => Ok(LayerColorLabel::Blue),
+            6 => Ok(LayerColorLabel::Violet),
+            7 => Ok(LayerColorLabel::Gray),
+            _ => Err(AdditionalLayerInfoError::InvalidColorLabel { value })?,
+        }
+    }
+}
+
+/// Represents invalid data within an Additional Layer Information tagged block
+#[derive(Debug, Fail)]
+pub enum AdditionalLayerInfoError {
+    #[fail(
+        display = "{} is an invalid section divider type, must be 0, 1, 2, or 3.",
+        kind
+    )]
+    InvalidSectionDividerType { kind: u32 },
+
+    #[fail(display = "{} is an invalid layer color label.", value)]
+    InvalidColorLabel { value: u16 },
+}
+
+/// Decode the pixels of a single channel's image data.
+///
+/// The first two bytes of the channel data are a compression flag, followed by the (possibly
+/// compressed) pixels themselves. Raw (uncompressed) data is `width * height * bytes_per_sample`
+/// bytes, where `bytes_per_sample` comes from the document's `depth`; RLE-compressed data packs
+/// 1 byte per sample regardless of depth, since PackBits operates on raw bytes.
+///
+/// # [Adobe Docs](https://www.adobe.com/devnet-apps/photoshop/fileformatashtml/)
+///
+/// | Length   | Description                                                                 |
+/// |----------|------------------------------------------------------------------------------|
+/// | 2        | Compression method: 0 = raw data, 1 = RLE compressed, 2 = ZIP without prediction, 3 = ZIP with prediction |
+/// | Variable | The image data, compressed according to the compression method above.        |
+fn decode_channel_data(
+    bytes: &[u8],
+    variant: PsdFileVariant,
+    depth: PsdDepth,
+    width: u64,
+    height: u64,
+) -> Result<Vec<u8>, Error> {
+    let mut cursor = PsdCursor::new(bytes);
+    let compression = cursor.read_u16_be()?;
+
+    match compression {
+        0 => {
+            let bytes_per_sample = depth.bytes_per_sample()?;
+            Ok(cursor.read((width * height * bytes_per_sample) as usize)?.to_vec())
+        }
+        1 => decode_rle_channel_data(&mut cursor, variant, height),
+        _ => Err(ChannelDataError::UnsupportedCompression { compression })?,
+    }
+}
+
+/// Decode PackBits/RLE compressed channel data.
+///
+/// The compressed data begins with a table of per-scanline byte counts (one `u16` per row for
+/// PSD, `u32` for PSB), followed by each scanline PackBits-encoded in turn.
+///
+/// To decode a PackBits-encoded scanline we read a header byte `n`:
+///
+/// - `n >= 0`: copy the next `n + 1` bytes literally.
+/// - `n` between -1 and -127: read one byte and repeat it `1 - n` times.
+/// - `n == -128`: no-op.
+fn decode_rle_channel_data(
+    cursor: &mut PsdCursor,
+    variant: PsdFileVariant,
+    height: u64,
+) -> Result<Vec<u8>, Error> {
+    let mut row_lengths = Vec::with_capacity(height as usize);
+    for _ in 0..height {
+        let row_length = match variant {
+            PsdFileVariant::Psd => cursor.read_u16_be()? as usize,
+            PsdFileVariant::Psb => cursor.read_u32_be()? as usize,
+        };
+        row_lengths.push(row_length);
+    }
+
+    let mut pixels = vec![];
+    for row_length in row_lengths {
+        let row = cursor.read(row_length)?;
+        decode_packbits_row(row, &mut pixels)?;
+    }
+
+    Ok(pixels)
+}
+
+/// Decode a single PackBits-encoded scanline into `pixels`.
+///
+/// A run that needs more bytes than the declared row length holds (a truncated/malformed
+/// file) is reported as a `PsdCursorError::UnexpectedEof` rather than panicking on an
+/// out-of-bounds slice index. Bytes left over after a complete run are decoded as further runs.
+fn decode_packbits_row(row: &[u8], pixels: &mut Vec<u8>) -> Result<(), Error> {
+    let mut cursor = PsdCursor::new(row);
 
-    // We do not currently parse the blend mode key, skip it
-    cursor.read_exact(&mut four_bytes)?;
+    while cursor.position() < row.len() {
+        let header = cursor.read_u8()? as i8;
 
-    // We do not currently parse the opacity, skip it
-    cursor.read_exact(&mut one_byte)?;
+        if header >= 0 {
+            let count = header as usize + 1;
+            pixels.extend_from_slice(cursor.read(count)?);
+        } else if header != -128 {
+            let count = 1 - header as isize;
+            let byte = cursor.read_u8()?;
 
-    // We do not currently parse the clipping, skip it
-    cursor.read_exact(&mut one_byte)?;
+            for _ in 0..count {
+                pixels.push(byte);
+            }
+        }
+    }
 
-    // We do not currently parse the flags, skip it
-    cursor.read_exact(&mut one_byte)?;
+    Ok(())
+}
 
-    // We do not currently parse the filter, skip it
-    cursor.read_exact(&mut one_byte)?;
+/// The blend mode of a layer, controlling how it composites with the layers beneath it.
+///
+/// # [Adobe Docs](https://www.adobe.com/devnet-apps/photoshop/fileformatashtml/)
+///
+/// 'pass' = pass through, 'norm' = normal, 'diss' = dissolve, 'dark' = darken, 'mul ' =
+/// multiply, 'idiv' = color burn, 'lbrn' = linear burn, 'dkCl' = darker color, 'lite' =
+/// lighten, 'scrn' = screen, 'div ' = color dodge, 'lddg' = linear dodge, 'lgCl' = lighter
+/// color, 'over' = overlay, 'sLit' = soft light, 'hLit' = hard light, 'vLit' = vivid light,
+/// 'lLit' = linear light, 'pLit' = pin light, 'hMix' = hard mix, 'diff' = difference, 'smud' =
+/// exclusion, 'fsub' = subtract, 'fdiv' = divide, 'hue ' = hue, 'sat ' = saturation, 'colr' =
+/// color, 'lum ' = luminosity.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum BlendMode {
+    PassThrough,
+    Normal,
+    Dissolve,
+    Darken,
+    Multiply,
+    ColorBurn,
+    LinearBurn,
+    DarkerColor,
+    Lighten,
+    Screen,
+    ColorDodge,
+    LinearDodge,
+    LighterColor,
+    Overlay,
+    SoftLight,
+    HardLight,
+    VividLight,
+    LinearLight,
+    PinLight,
+    HardMix,
+    Difference,
+    Exclusion,
+    Subtract,
+    Divide,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
 
-    // We do not currently use the length of the extra data field, skip it
-    cursor.read_exact(&mut four_bytes)?;
+/// Represents an invalid blend mode key
+#[derive(Debug, Fail)]
+pub enum BlendModeError {
+    #[fail(display = "{} is not a valid blend mode key.", key)]
+    InvalidBlendMode { key: String },
+}
 
-    let name = "".to_string();
+impl BlendMode {
+    /// Create a new BlendMode from its 4 character blend mode key
+    pub fn new(key: [u8; 4]) -> Result<BlendMode, Error> {
+        match &key {
+            b"pass" => Ok(BlendMode::PassThrough),
+            b"norm" => Ok(BlendMode::Normal),
+            b"diss" => Ok(BlendMode::Dissolve),
+            b"dark" => Ok(BlendMode::Darken),
+            b"mul " => Ok(BlendMode::Multiply),
+            b"idiv" => Ok(BlendMode::ColorBurn),
+            b"lbrn" => Ok(BlendMode::LinearBurn),
+            b"dkCl" => Ok(BlendMode::DarkerColor),
+            b"lite" => Ok(BlendMode::Lighten),
+            b"scrn" => Ok(BlendMode::Screen),
+            b"div " => Ok(BlendMode::ColorDodge),
+            b"lddg" => Ok(BlendMode::LinearDodge),
+            b"lgCl" => Ok(BlendMode::LighterColor),
+            b"over" => Ok(BlendMode::Overlay),
+            b"sLit" => Ok(BlendMode::SoftLight),
+            b"hLit" => Ok(BlendMode::HardLight),
+            b"vLit" => Ok(BlendMode::VividLight),
+            b"lLit" => Ok(BlendMode::LinearLight),
+            b"pLit" => Ok(BlendMode::PinLight),
+            b"hMix" => Ok(BlendMode::HardMix),
+            b"diff" => Ok(BlendMode::Difference),
+            b"smud" => Ok(BlendMode::Exclusion),
+            b"fsub" => Ok(BlendMode::Subtract),
+            b"fdiv" => Ok(BlendMode::Divide),
+            b"hue " => Ok(BlendMode::Hue),
+            b"sat " => Ok(BlendMode::Saturation),
+            b"colr" => Ok(BlendMode::Color),
+            b"lum " => Ok(BlendMode::Luminosity),
+            _ => Err(BlendModeError::InvalidBlendMode {
+                key: String::from_utf8_lossy(&key).to_string(),
+            })?,
+        }
+    }
+}
 
-    Ok(LayerRecord { name, channels })
+/// An error when decoding a channel's image data.
+#[derive(Debug, Fail)]
+pub enum ChannelDataError {
+    #[fail(
+        display = "Compression method {} is not yet supported. Only raw (0) and RLE (1) are currently implemented.",
+        compression
+    )]
+    UnsupportedCompression { compression: u16 },
 }
 
 /// A layer record within the layer info section
@@ -159,12 +677,77 @@ struct LayerRecord {
     /// The name of the layer
     name: String,
     /// The channels that this record has and the number of bytes in each channel.
-    channels: Vec<(PsdLayerChannel, u32)>,
+    channels: Vec<(PsdLayerChannel, u64)>,
+    /// The top coordinate of the rectangle that contains the layer's contents
+    layer_top: i32,
+    /// The left coordinate of the rectangle that contains the layer's contents
+    layer_left: i32,
+    /// The bottom coordinate of the rectangle that contains the layer's contents
+    layer_bottom: i32,
+    /// The right coordinate of the rectangle that contains the layer's contents
+    layer_right: i32,
+    /// How this layer should be blended with the layers below it
+    blend_mode: BlendMode,
+    /// The layer's opacity. 0 = transparent ... 255 = opaque
+    opacity: u8,
+    /// Whether this layer clips to the layer below it (true = non-base, false = base)
+    clipping: bool,
+    /// Whether the layer's transparency is locked
+    transparency_protected: bool,
+    /// Whether the layer is visible
+    visible: bool,
+    /// Whether the layer's pixel data is irrelevant to the appearance of the document
+    pixel_data_irrelevant: bool,
+    /// The layer's id, from the 'lyid' tagged block, if present
+    layer_id: Option<u32>,
+    /// The layer's section divider type, from the 'lsct'/'lsdk' tagged block, if present
+    section_divider: Option<SectionDivider>,
+    /// The layer's color label, from the 'lclr' tagged block, if present
+    color_label: Option<LayerColorLabel>,
+}
+
+impl LayerRecord {
+    /// The width of the layer, in pixels.
+    ///
+    /// This is `u64` so that `width * height * bytes_per_sample` in `decode_channel_data` is
+    /// computed in 64 bits. We subtract in `i64` (rather than the fields' native
+    /// `i32`) because `read_layer_record` only validates that `right >= left`, not that the
+    /// difference fits back into `i32`.
+    fn width(&self) -> u64 {
+        (self.layer_right as i64 - self.layer_left as i64) as u64
+    }
+
+    /// The height of the layer, in pixels.
+    ///
+    /// This is `u64` so that `width * height * bytes_per_sample` in `decode_channel_data` is
+    /// computed in 64 bits. We subtract in `i64` (rather than the fields' native
+    /// `i32`) because `read_layer_record` only validates that `bottom >= top`, not that the
+    /// difference fits back into `i32`.
+    fn height(&self) -> u64 {
+        (self.layer_bottom as i64 - self.layer_top as i64) as u64
+    }
+}
+
+/// Represents an invalid layer record
+#[derive(Debug, Fail)]
+pub enum LayerRecordError {
+    #[fail(
+        display = "Invalid layer rectangle: top {}, left {}, bottom {}, right {} (right must be >= left and bottom must be >= top).",
+        top, left, bottom, right
+    )]
+    InvalidRectangle {
+        top: i32,
+        left: i32,
+        bottom: i32,
+        right: i32,
+    },
 }
 
 /// Information about a layer in a PSD file.
 #[derive(Debug)]
 pub struct PsdLayer {
+    /// The name of the layer
+    name: String,
     /// The channels of the layer, stored separately.
     ///
     /// You can combine these channels into a final image. For example, you might combine
@@ -173,41 +756,581 @@ pub struct PsdLayer {
     ///
     /// Storing the channels separately allows for this flexability.
     channels: HashMap<PsdLayerChannel, Vec<u8>>,
+    /// How this layer should be blended with the layers below it
+    blend_mode: BlendMode,
+    /// The layer's opacity. 0 = transparent ... 255 = opaque
+    opacity: u8,
+    /// Whether this layer clips to the layer below it (true = non-base, false = base)
+    clipping: bool,
+    /// Whether the layer's transparency is locked
+    transparency_protected: bool,
+    /// Whether the layer is visible
+    visible: bool,
+    /// Whether the layer's pixel data is irrelevant to the appearance of the document
+    pixel_data_irrelevant: bool,
+    /// The layer's id, from the 'lyid' tagged block, if present
+    layer_id: Option<u32>,
+    /// The layer's section divider type, from the 'lsct'/'lsdk' tagged block, if present
+    section_divider: Option<SectionDivider>,
+    /// The layer's color label, from the 'lclr' tagged block, if present
+    color_label: Option<LayerColorLabel>,
+}
+
+impl PsdLayer {
+    /// The name of the layer
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The channels that are present in this layer along with their decoded pixel bytes.
+    pub fn channels(&self) -> &HashMap<PsdLayerChannel, Vec<u8>> {
+        &self.channels
+    }
+
+    /// How this layer should be blended with the layers below it
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// The layer's opacity. 0 = transparent ... 255 = opaque
+    pub fn opacity(&self) -> u8 {
+        self.opacity
+    }
+
+    /// Whether this layer clips to the layer below it (true = non-base, false = base)
+    pub fn clipping(&self) -> bool {
+        self.clipping
+    }
+
+    /// Whether the layer's transparency is locked
+    pub fn transparency_protected(&self) -> bool {
+        self.transparency_protected
+    }
+
+    /// Whether the layer is visible
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Whether the layer's pixel data is irrelevant to the appearance of the document
+    pub fn pixel_data_irrelevant(&self) -> bool {
+        self.pixel_data_irrelevant
+    }
+
+    /// The layer's id, if the 'lyid' tagged block was present
+    pub fn layer_id(&self) -> Option<u32> {
+        self.layer_id
+    }
+
+    /// The layer's section divider type, if the 'lsct'/'lsdk' tagged block was present
+    pub fn section_divider(&self) -> Option<SectionDivider> {
+        self.section_divider
+    }
+
+    /// The layer's color label, if the 'lclr' tagged block was present
+    pub fn color_label(&self) -> Option<LayerColorLabel> {
+        self.color_label
+    }
 }
 
 /// The different kinds of channels in a layer (red, green, blue, ...).
-#[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+///
+/// Which channel a given id refers to is dependent on the document's color mode, e.g. id `0`
+/// is Gray in a grayscale document but Cyan in a CMYK one. See [`PsdLayerChannel::new`].
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[allow(missing_docs)]
-enum PsdLayerChannel {
-    Red = 0,
-    Green = 1,
-    Blue = 2,
-    TransparencyMask = -1,
-    UserSuppliedLayerMask = -2,
-    RealUserSuppliedLayerMask = -3,
+pub enum PsdLayerChannel {
+    Red,
+    Green,
+    Blue,
+    Gray,
+    Cyan,
+    Magenta,
+    Yellow,
+    Black,
+    Lightness,
+    A,
+    B,
+    TransparencyMask,
+    UserSuppliedLayerMask,
+    RealUserSuppliedLayerMask,
 }
 
 /// Represents an invalid layer channel id
 #[derive(Debug, Fail)]
 pub enum PsdLayerChannelError {
     #[fail(
-        display = "{} is an invalid channel id, must be 0, 1, 2, -1, -2, or -3.",
-        channel_id
+        display = "{} is an invalid channel id for color mode {:?}.",
+        channel_id, color_mode
     )]
-    InvalidChannel { channel_id: i8 },
+    InvalidChannel {
+        channel_id: i8,
+        color_mode: PsdColorMode,
+    },
 }
 
 impl PsdLayerChannel {
-    /// Create a new PsdLayerChannel
-    pub fn new(channel_id: i8) -> Result<PsdLayerChannel, Error> {
+    /// Create a new PsdLayerChannel from its id and the document's color mode, since channel
+    /// ids are color-mode dependent (e.g. CMYK uses 0-3, grayscale uses 0, Lab uses 0-2 with
+    /// different meanings than RGB). The transparency/mask ids (-1, -2, -3) are constant across
+    /// all color modes.
+    pub fn new(channel_id: i8, color_mode: PsdColorMode) -> Result<PsdLayerChannel, Error> {
         match channel_id {
-            0 => Ok(PsdLayerChannel::Red),
-            1 => Ok(PsdLayerChannel::Green),
-            2 => Ok(PsdLayerChannel::Blue),
-            -1 => Ok(PsdLayerChannel::TransparencyMask),
-            -2 => Ok(PsdLayerChannel::UserSuppliedLayerMask),
-            -3 => Ok(PsdLayerChannel::RealUserSuppliedLayerMask),
-            _ => Err(PsdLayerChannelError::InvalidChannel { channel_id })?,
+            -1 => return Ok(PsdLayerChannel::TransparencyMask),
+            -2 => return Ok(PsdLayerChannel::UserSuppliedLayerMask),
+            -3 => return Ok(PsdLayerChannel::RealUserSuppliedLayerMask),
+            _ => {}
+        }
+
+        match (color_mode, channel_id) {
+            (PsdColorMode::Grayscale, 0)
+            | (PsdColorMode::Bitmap, 0)
+            | (PsdColorMode::Duotone, 0) => Ok(PsdLayerChannel::Gray),
+            (PsdColorMode::Rgb, 0) => Ok(PsdLayerChannel::Red),
+            (PsdColorMode::Rgb, 1) => Ok(PsdLayerChannel::Green),
+            (PsdColorMode::Rgb, 2) => Ok(PsdLayerChannel::Blue),
+            (PsdColorMode::Cmyk, 0) => Ok(PsdLayerChannel::Cyan),
+            (PsdColorMode::Cmyk, 1) => Ok(PsdLayerChannel::Magenta),
+            (PsdColorMode::Cmyk, 2) => Ok(PsdLayerChannel::Yellow),
+            (PsdColorMode::Cmyk, 3) => Ok(PsdLayerChannel::Black),
+            (PsdColorMode::Lab, 0) => Ok(PsdLayerChannel::Lightness),
+            (PsdColorMode::Lab, 1) => Ok(PsdLayerChannel::A),
+            (PsdColorMode::Lab, 2) => Ok(PsdLayerChannel::B),
+            _ => Err(PsdLayerChannelError::InvalidChannel {
+                channel_id,
+                color_mode,
+            })?,
+        }
+    }
+}
+
+/// Whether a layer and mask information section came from a PSD or a PSB file. PSB widens some
+/// of the 4-byte length fields that PSD uses to 8 bytes, to support documents that are larger
+/// than 4 bytes can address.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PsdFileVariant {
+    /// A standard Photoshop document
+    Psd,
+    /// A large document Photoshop file, `.psb`
+    Psb,
+}
+
+/// Read a length field whose size depends on the file variant: 4 bytes for PSD, 8 bytes for
+/// PSB.
+fn read_section_length(cursor: &mut PsdCursor, variant: PsdFileVariant) -> Result<u64, Error> {
+    match variant {
+        PsdFileVariant::Psd => Ok(cursor.read_u32_be()? as u64),
+        PsdFileVariant::Psb => cursor.read_u64_be(),
+    }
+}
+
+/// The color mode of the overall PSD document, which determines the meaning of each layer
+/// channel's id.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum PsdColorMode {
+    Bitmap,
+    Grayscale,
+    Indexed,
+    Rgb,
+    Cmyk,
+    Multichannel,
+    Duotone,
+    Lab,
+}
+
+/// Represents an invalid color mode
+#[derive(Debug, Fail)]
+pub enum PsdColorModeError {
+    #[fail(display = "{} is not a valid color mode.", mode)]
+    InvalidColorMode { mode: u16 },
+}
+
+impl PsdColorMode {
+    /// Create a new PsdColorMode from its color mode data value
+    pub fn new(mode: u16) -> Result<PsdColorMode, Error> {
+        match mode {
+            0 => Ok(PsdColorMode::Bitmap),
+            1 => Ok(PsdColorMode::Grayscale),
+            2 => Ok(PsdColorMode::Indexed),
+            3 => Ok(PsdColorMode::Rgb),
+            4 => Ok(PsdColorMode::Cmyk),
+            7 => Ok(PsdColorMode::Multichannel),
+            8 => Ok(PsdColorMode::Duotone),
+            9 => Ok(PsdColorMode::Lab),
+            _ => Err(PsdColorModeError::InvalidColorMode { mode })?,
+        }
+    }
+}
+
+/// The bit depth of each channel sample in the document, which determines how many bytes a
+/// single pixel occupies in an uncompressed ("raw") channel.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum PsdDepth {
+    One,
+    Eight,
+    Sixteen,
+    ThirtyTwo,
+}
+
+/// Represents an invalid depth, or a depth whose raw channel layout we don't support decoding
+#[derive(Debug, Fail)]
+pub enum PsdDepthError {
+    #[fail(display = "{} is not a valid depth.", depth)]
+    InvalidDepth { depth: u16 },
+
+    #[fail(
+        display = "Raw (uncompressed) channel data is not supported for 1-bit (bitmap) depth, since samples aren't byte-aligned."
+    )]
+    UnsupportedRawDepth,
+}
+
+impl PsdDepth {
+    /// Create a new PsdDepth from its depth data value
+    pub fn new(depth: u16) -> Result<PsdDepth, Error> {
+        match depth {
+            1 => Ok(PsdDepth::One),
+            8 => Ok(PsdDepth::Eight),
+            16 => Ok(PsdDepth::Sixteen),
+            32 => Ok(PsdDepth::ThirtyTwo),
+            _ => Err(PsdDepthError::InvalidDepth { depth })?,
+        }
+    }
+
+    /// The number of bytes that a single channel sample occupies in raw (uncompressed) channel
+    /// data. 1-bit depth isn't byte-aligned, so it has no single answer here.
+    fn bytes_per_sample(self) -> Result<u64, Error> {
+        match self {
+            PsdDepth::One => Err(PsdDepthError::UnsupportedRawDepth)?,
+            PsdDepth::Eight => Ok(1),
+            PsdDepth::Sixteen => Ok(2),
+            PsdDepth::ThirtyTwo => Ok(4),
+        }
+    }
+}
+
+/// A small cursor over a byte slice, used to parse the layer and mask information section
+/// without scattering manual scratch buffers and unchecked position arithmetic through the
+/// parsing code. Every read is length-checked and returns a descriptive error on underrun,
+/// instead of silently truncating or panicking on a malformed file.
+struct PsdCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+/// An error reading past the end of a PsdCursor's bytes
+#[derive(Debug, Fail)]
+pub enum PsdCursorError {
+    #[fail(
+        display = "Tried to read {} bytes at position {} but only {} bytes are left.",
+        requested, position, remaining
+    )]
+    UnexpectedEof {
+        position: usize,
+        requested: usize,
+        remaining: usize,
+    },
+}
+
+impl<'a> PsdCursor<'a> {
+    fn new(bytes: &'a [u8]) -> PsdCursor<'a> {
+        PsdCursor { bytes, position: 0 }
+    }
+
+    /// The cursor's current position within its bytes.
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Look at the next `n` bytes without advancing the cursor.
+    fn peek(&self, n: usize) -> Result<&'a [u8], Error> {
+        self.position
+            .checked_add(n)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or_else(|| {
+                PsdCursorError::UnexpectedEof {
+                    position: self.position,
+                    requested: n,
+                    remaining: self.bytes.len().saturating_sub(self.position),
+                }
+                .into()
+            })
+    }
+
+    /// Read the next `n` bytes, advancing the cursor past them.
+    fn read(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let bytes = self.peek(n)?;
+        self.position += n;
+        Ok(bytes)
+    }
+
+    /// Advance the cursor by `n` bytes without returning them.
+    fn skip(&mut self, n: usize) -> Result<(), Error> {
+        self.read(n)?;
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u16_be(&mut self) -> Result<u16, Error> {
+        Ok(as_u16_be(self.read(2)?))
+    }
+
+    fn read_u32_be(&mut self) -> Result<u32, Error> {
+        Ok(as_u32_be(self.read(4)?))
+    }
+
+    fn read_u64_be(&mut self) -> Result<u64, Error> {
+        let bytes = self.read(8)?;
+
+        Ok(((bytes[0] as u64) << 56)
+            | ((bytes[1] as u64) << 48)
+            | ((bytes[2] as u64) << 40)
+            | ((bytes[3] as u64) << 32)
+            | ((bytes[4] as u64) << 24)
+            | ((bytes[5] as u64) << 16)
+            | ((bytes[6] as u64) << 8)
+            | (bytes[7] as u64))
+    }
+
+    /// Read a Pascal string: a 1-byte length followed by that many bytes, with the whole field
+    /// (length byte included) padded to a multiple of 4 bytes.
+    fn read_pascal_string(&mut self) -> Result<String, Error> {
+        let len = self.read_u8()? as usize;
+        let name = String::from_utf8_lossy(self.read(len)?).to_string();
+
+        let padded_len = (1 + len + 3) / 4 * 4;
+        self.skip(padded_len - 1 - len)?;
+
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_packbits_row_literal_run() {
+        // Header 2 = copy the next 3 bytes literally.
+        let row = [2, 10, 20, 30];
+        let mut pixels = vec![];
+
+        decode_packbits_row(&row, &mut pixels).unwrap();
+
+        assert_eq!(pixels, vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn decode_packbits_row_repeat_run() {
+        // Header -3 (0xFD) = read one byte and repeat it 1 - (-3) = 4 times.
+        let row = [0xFDu8, 7];
+        let mut pixels = vec![];
+
+        decode_packbits_row(&row, &mut pixels).unwrap();
+
+        assert_eq!(pixels, vec![7, 7, 7, 7]);
+    }
+
+    #[test]
+    fn decode_packbits_row_noop_header_is_skipped() {
+        // -128 (0x80) is a no-op, the literal run after it should still decode.
+        let row = [0x80u8, 0, 5];
+        let mut pixels = vec![];
+
+        decode_packbits_row(&row, &mut pixels).unwrap();
+
+        assert_eq!(pixels, vec![5]);
+    }
+
+    #[test]
+    fn decode_packbits_row_truncated_literal_run_errors_instead_of_panicking() {
+        // Header 2 claims 3 literal bytes follow, but only 1 is present.
+        let row = [2, 10];
+        let mut pixels = vec![];
+
+        assert!(decode_packbits_row(&row, &mut pixels).is_err());
+    }
+
+    #[test]
+    fn decode_rle_channel_data_psd_uses_u16_row_lengths() {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&(3u16).to_be_bytes()); // row 0 length (1 header byte + 2 data bytes)
+        bytes.extend_from_slice(&(3u16).to_be_bytes()); // row 1 length
+        bytes.extend_from_slice(&[1, 9, 9]); // row 0: copy 2 literal bytes, 9 and 9
+        bytes.extend_from_slice(&[1, 4, 4]); // row 1: copy 2 literal bytes, 4 and 4
+
+        let mut cursor = PsdCursor::new(&bytes);
+        let pixels = decode_rle_channel_data(&mut cursor, PsdFileVariant::Psd, 2).unwrap();
+
+        assert_eq!(pixels, vec![9, 9, 4, 4]);
+    }
+
+    #[test]
+    fn decode_rle_channel_data_psb_uses_u32_row_lengths() {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&(3u32).to_be_bytes()); // row 0 length (1 header byte + 2 data bytes)
+        bytes.extend_from_slice(&[1, 9, 9]); // row 0: copy 2 literal bytes, 9 and 9
+
+        let mut cursor = PsdCursor::new(&bytes);
+        let pixels = decode_rle_channel_data(&mut cursor, PsdFileVariant::Psb, 1).unwrap();
+
+        assert_eq!(pixels, vec![9, 9]);
+    }
+
+    #[test]
+    fn pascal_string_pads_to_a_multiple_of_4_bytes() {
+        // Length byte (1) + "a" (1 byte) = 2 bytes, padded up to 4.
+        let bytes = [1, b'a', 0, 0];
+        let mut cursor = PsdCursor::new(&bytes);
+
+        assert_eq!(cursor.read_pascal_string().unwrap(), "a");
+        assert_eq!(cursor.position(), 4);
+    }
+
+    #[test]
+    fn pascal_string_with_no_padding_needed() {
+        // Length byte (1) + "abc" (3 bytes) = 4 bytes, already a multiple of 4.
+        let bytes = [3, b'a', b'b', b'c'];
+        let mut cursor = PsdCursor::new(&bytes);
+
+        assert_eq!(cursor.read_pascal_string().unwrap(), "abc");
+        assert_eq!(cursor.position(), 4);
+    }
+
+    #[test]
+    fn cursor_read_past_end_returns_unexpected_eof() {
+        let bytes = [1, 2, 3];
+        let mut cursor = PsdCursor::new(&bytes);
+
+        cursor.skip(3).unwrap();
+
+        assert!(cursor.read(1).is_err());
+    }
+
+    /// A minimal `LayerRecord`/`PsdLayer` pair for exercising `build_layer_tree`, where only the
+    /// name and section divider matter.
+    fn test_layer(name: &str, section_divider: Option<SectionDivider>) -> (LayerRecord, PsdLayer) {
+        let record = LayerRecord {
+            name: name.to_string(),
+            channels: vec![],
+            layer_top: 0,
+            layer_left: 0,
+            layer_bottom: 1,
+            layer_right: 1,
+            blend_mode: BlendMode::Normal,
+            opacity: 255,
+            clipping: false,
+            transparency_protected: false,
+            visible: true,
+            pixel_data_irrelevant: false,
+            layer_id: None,
+            section_divider,
+            color_label: None,
+        };
+
+        let layer = PsdLayer {
+            name: name.to_string(),
+            channels: HashMap::new(),
+            blend_mode: BlendMode::Normal,
+            opacity: 255,
+            clipping: false,
+            transparency_protected: false,
+            visible: true,
+            pixel_data_irrelevant: false,
+            layer_id: None,
+            section_divider: None,
+            color_label: None,
+        };
+
+        (record, layer)
+    }
+
+    fn layer_name(member: &PsdGroupMember) -> &str {
+        match member {
+            PsdGroupMember::Layer(layer) => layer.name(),
+            PsdGroupMember::Group(_) => panic!("expected a Layer, found a Group"),
         }
     }
+
+    fn group(member: &PsdGroupMember) -> &PsdGroupLayer {
+        match member {
+            PsdGroupMember::Group(group) => group,
+            PsdGroupMember::Layer(_) => panic!("expected a Group, found a Layer"),
+        }
+    }
+
+    #[test]
+    fn build_layer_tree_flat_layer_list() {
+        // A flat, bottom-to-top list of layers with no section dividers at all.
+        let records_and_layers = vec![
+            test_layer("bottom", None),
+            test_layer("middle", None),
+            test_layer("top", None),
+        ];
+
+        let tree = build_layer_tree(records_and_layers);
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(layer_name(&tree[0]), "bottom");
+        assert_eq!(layer_name(&tree[1]), "middle");
+        assert_eq!(layer_name(&tree[2]), "top");
+    }
+
+    #[test]
+    fn build_layer_tree_single_group() {
+        // Bottom-to-top: the bounding divider opens the group, "content" is its only child, and
+        // the folder's own record (at the top) closes the group and names it.
+        let records_and_layers = vec![
+            test_layer("bound", Some(SectionDivider::BoundingSectionDivider)),
+            test_layer("content", None),
+            test_layer("Group", Some(SectionDivider::OpenFolder)),
+        ];
+
+        let tree = build_layer_tree(records_and_layers);
+
+        assert_eq!(tree.len(), 1);
+        let group = group(&tree[0]);
+        assert_eq!(group.name(), "Group");
+        assert!(group.opened());
+        assert_eq!(group.children().len(), 1);
+        assert_eq!(layer_name(&group.children()[0]), "content");
+    }
+
+    #[test]
+    fn build_layer_tree_nested_groups() {
+        // An outer (closed) group containing an inner (open) group plus a sibling layer:
+        //
+        // Outer
+        //   Inner
+        //     inner-content
+        //   outer-content
+        let records_and_layers = vec![
+            test_layer("outer-bound", Some(SectionDivider::BoundingSectionDivider)),
+            test_layer("inner-bound", Some(SectionDivider::BoundingSectionDivider)),
+            test_layer("inner-content", None),
+            test_layer("Inner", Some(SectionDivider::OpenFolder)),
+            test_layer("outer-content", None),
+            test_layer("Outer", Some(SectionDivider::ClosedFolder)),
+        ];
+
+        let tree = build_layer_tree(records_and_layers);
+
+        assert_eq!(tree.len(), 1);
+        let outer = group(&tree[0]);
+        assert_eq!(outer.name(), "Outer");
+        assert!(!outer.opened());
+        assert_eq!(outer.children().len(), 2);
+
+        let inner = group(&outer.children()[0]);
+        assert_eq!(inner.name(), "Inner");
+        assert!(inner.opened());
+        assert_eq!(inner.children().len(), 1);
+        assert_eq!(layer_name(&inner.children()[0]), "inner-content");
+
+        assert_eq!(layer_name(&outer.children()[1]), "outer-content");
+    }
 }